@@ -0,0 +1,289 @@
+/// This file is part of brainrust and is copyright Peter Beard
+/// Licensed under the GPL v3, see LICENSE for details
+///
+/// Translates a compiled `Op` program into standalone NASM-syntax x86-64
+/// assembly instead of interpreting it. The data pointer lives in `rdx`
+/// and the tape is a fixed `.bss` reservation, so the same run-length
+/// folding `compile` already did for the interpreter keeps the emitted
+/// assembly compact.
+use std::collections::HashMap;
+
+use crate::tape::{Bounds, CellWidth, EofBehavior, Overflow, TapeConfig};
+use crate::Op;
+
+/// How a cell width maps onto NASM operand sizes and registers
+struct Width {
+    /// NASM size specifier for a memory operand, e.g. `word [rdx]`
+    ptr_size: &'static str,
+    /// The sub-register of `rax` sized to match, e.g. `ax`
+    acc: &'static str,
+    bytes: i64,
+    max: i64,
+}
+
+fn width_of(cell_width: CellWidth) -> Width {
+    match cell_width {
+        CellWidth::U8 => Width { ptr_size: "byte", acc: "al", bytes: 1, max: 0xFF },
+        CellWidth::U16 => Width { ptr_size: "word", acc: "ax", bytes: 2, max: 0xFFFF },
+        CellWidth::U32 => Width { ptr_size: "dword", acc: "eax", bytes: 4, max: 0xFFFF_FFFF },
+    }
+}
+
+/// Generate a complete NASM source file for `program`
+///
+/// The output can be assembled and linked into a standalone executable,
+/// e.g. `nasm -f elf64 out.asm && ld out.o -o out`. Since a `.bss`
+/// reservation can't grow at runtime, only `Bounds::Fixed` tapes are
+/// supported; `Overflow::Saturate` would need a compare-and-clamp around
+/// every arithmetic op and isn't implemented either. Both are rejected
+/// with an error rather than silently emitting code that ignores them.
+/// A pointer move or `AddMul` that would land outside the `.bss`
+/// reservation traps at runtime (see `emit_bounds_check`), the same
+/// "Data pointer moved outside the fixed-size tape" failure `Tape::move_by`
+/// reports when interpreting - so `--emit=asm` can't silently read or
+/// write past the tape the way an unchecked pointer would.
+pub(crate) fn generate(program: &[Op], config: &TapeConfig) -> Result<String, String> {
+    let cells = match config.bounds {
+        Bounds::Fixed(n) => n.max(1),
+        Bounds::Unbounded => {
+            return Err("the asm backend needs a fixed-size tape (pass --tape=N); \
+                an unbounded tape can't be represented as a static .bss reservation".to_string());
+        },
+    };
+    if config.overflow == Overflow::Saturate {
+        return Err("the asm backend doesn't support --overflow=saturate; \
+            only wrapping arithmetic is implemented".to_string());
+    }
+    let width = width_of(config.cell_width);
+    let total_bytes = cells as i64 * width.bytes;
+
+    let mut out = String::new();
+    out.push_str("section .data\n");
+    out.push_str("    oob_msg: db \"Data pointer moved outside the fixed-size tape\", 10\n");
+    out.push_str("    oob_msg_len equ $ - oob_msg\n\n");
+    out.push_str("section .bss\n");
+    out.push_str(&format!("    data: resb {}\n", total_bytes));
+    out.push_str("    inbuf: resb 1\n\n");
+    out.push_str("section .text\n");
+    out.push_str("    global _start\n\n");
+    out.push_str("_start:\n");
+    out.push_str("    mov rdx, data\n");
+    // r14/r15 hold the first and last valid cell addresses so every bounds
+    // check is a compare against a register instead of a 64-bit immediate
+    // (which `cmp` can't encode); `syscall` only clobbers rcx/r11/rax, so
+    // both survive the `.`/`,` syscalls untouched
+    out.push_str("    mov r14, data\n");
+    out.push_str("    mov r15, data\n");
+    out.push_str(&format!("    add r15, {}\n", total_bytes - width.bytes));
+
+    // Loops are compiled to a pair of labels per bracket; `loop_labels` maps
+    // the index of each JumpIfZero/JumpIfNonZero to the shared loop number
+    // so the matching label can be found when we reach its partner.
+    let mut loop_labels: HashMap<usize, usize> = HashMap::new();
+    let mut next_loop = 0;
+
+    for (idx, op) in program.iter().enumerate() {
+        match *op {
+            Op::Add(delta) => emit_add(&mut out, &format!("{} [rdx]", width.ptr_size), delta, &width),
+            Op::Move { delta, min, max, .. } => {
+                emit_bounds_check(&mut out, min as i64 * width.bytes, max as i64 * width.bytes);
+                let delta = delta as i64 * width.bytes;
+                if delta > 0 {
+                    out.push_str(&format!("    add rdx, {}\n", delta));
+                } else if delta < 0 {
+                    out.push_str(&format!("    sub rdx, {}\n", -delta));
+                }
+            },
+            Op::Clear => out.push_str(&format!("    mov {} [rdx], 0\n", width.ptr_size)),
+            Op::AddMul { offset, factor, .. } => {
+                // the pointer itself never moves here, but the write still
+                // lands `offset` cells away, so it needs the same check a
+                // `Move` to that offset and back would get
+                emit_bounds_check(&mut out, offset.min(0) as i64 * width.bytes, offset.max(0) as i64 * width.bytes);
+                // `movzx` requires a destination wider than its source, so a
+                // 32-bit cell (already exactly `eax`'s width) needs a plain
+                // `mov` instead - it zero-extends into the rest of `rax` anyway
+                if width.bytes == 4 {
+                    out.push_str(&format!("    mov eax, {} [rdx]\n", width.ptr_size));
+                } else {
+                    out.push_str(&format!("    movzx eax, {} [rdx]\n", width.ptr_size));
+                }
+                out.push_str(&format!("    mov ecx, {}\n", wrap_to_width(factor, &width)));
+                out.push_str("    imul eax, ecx\n");
+                let addr = rdx_offset(offset as i64 * width.bytes);
+                out.push_str(&format!("    add {} [{}], {}\n", width.ptr_size, addr, width.acc));
+            },
+            Op::Out => {
+                // sys_write(stdout, rdx, 1): BF `.` only ever emits the low
+                // byte of the cell, which - x86-64 being little-endian -
+                // always sits at the lowest address of a wider cell too
+                out.push_str("    mov rax, 1\n");
+                out.push_str("    mov rdi, 1\n");
+                out.push_str("    mov rsi, rdx\n");
+                out.push_str("    push rdx\n");
+                out.push_str("    mov rdx, 1\n");
+                out.push_str("    syscall\n");
+                out.push_str("    pop rdx\n");
+            },
+            Op::In { .. } => {
+                let n = next_loop;
+                next_loop += 1;
+                // sys_read(stdin, inbuf, 1), not directly into [rdx]: BF `,`
+                // always replaces the whole cell with the byte read (never
+                // just its low byte), so the result needs zero-extending
+                out.push_str("    xor rax, rax\n");
+                out.push_str("    xor rdi, rdi\n");
+                out.push_str("    mov rsi, inbuf\n");
+                out.push_str("    push rdx\n");
+                out.push_str("    mov rdx, 1\n");
+                out.push_str("    syscall\n");
+                out.push_str("    pop rdx\n");
+                out.push_str("    cmp rax, 1\n");
+                out.push_str(&format!("    jne .eof_{}\n", n));
+                out.push_str("    movzx eax, byte [inbuf]\n");
+                out.push_str(&format!("    mov {} [rdx], {}\n", width.ptr_size, width.acc));
+                out.push_str(&format!("    jmp .eof_done_{}\n", n));
+                out.push_str(&format!(".eof_{}:\n", n));
+                match config.eof {
+                    EofBehavior::Zero => out.push_str(&format!("    mov {} [rdx], 0\n", width.ptr_size)),
+                    EofBehavior::MinusOne => out.push_str(&format!("    mov {} [rdx], {}\n", width.ptr_size, width.max)),
+                    EofBehavior::Unchanged => {},
+                }
+                out.push_str(&format!(".eof_done_{}:\n", n));
+            },
+            Op::JumpIfZero(target) => {
+                let n = next_loop;
+                next_loop += 1;
+                loop_labels.insert(idx, n);
+                loop_labels.insert(target, n);
+                out.push_str(&format!("loop_start_{}:\n", n));
+                out.push_str(&format!("    cmp {} [rdx], 0\n", width.ptr_size));
+                out.push_str(&format!("    je loop_end_{}\n", n));
+            },
+            Op::JumpIfNonZero(_) => {
+                let n = *loop_labels.get(&idx)
+                    .expect("JumpIfNonZero's matching JumpIfZero always registers a label first");
+                out.push_str(&format!("    cmp {} [rdx], 0\n", width.ptr_size));
+                out.push_str(&format!("    jne loop_start_{}\n", n));
+                out.push_str(&format!("loop_end_{}:\n", n));
+            },
+        }
+    }
+
+    out.push_str("    mov rax, 60\n");
+    out.push_str("    xor rdi, rdi\n");
+    out.push_str("    syscall\n");
+
+    // Only reached via a bounds-check jump; writes the same failure message
+    // `Tape::move_by` returns and exits non-zero, mirroring the interpreter.
+    // Not a `.`-prefixed local label: NASM scopes those to the preceding
+    // `loop_start_N`/`loop_end_N`, so checks inside loops couldn't reach it
+    out.push_str("oob_trap:\n");
+    out.push_str("    mov rax, 1\n");
+    out.push_str("    mov rdi, 2\n");
+    out.push_str("    mov rsi, oob_msg\n");
+    out.push_str("    mov rdx, oob_msg_len\n");
+    out.push_str("    syscall\n");
+    out.push_str("    mov rax, 60\n");
+    out.push_str("    mov rdi, 1\n");
+    out.push_str("    syscall\n");
+    Ok(out)
+}
+
+/// Emit a compare-and-trap that rejects a pointer excursion outside the
+/// fixed-size tape before it happens, mirroring `Tape::move_by`'s bounds
+/// check in the interpreter. `min_bytes`/`max_bytes` are the furthest
+/// left/right of the current `rdx` this op reaches, in bytes
+fn emit_bounds_check(out: &mut String, min_bytes: i64, max_bytes: i64) {
+    out.push_str(&format!("    lea rax, [{}]\n", rdx_offset(min_bytes)));
+    out.push_str("    cmp rax, r14\n");
+    out.push_str("    jl oob_trap\n");
+    out.push_str(&format!("    lea rax, [{}]\n", rdx_offset(max_bytes)));
+    out.push_str("    cmp rax, r15\n");
+    out.push_str("    jg oob_trap\n");
+}
+
+/// NASM `[rdx+N]`/`[rdx-N]` displacement syntax for a byte offset from `rdx`
+fn rdx_offset(bytes: i64) -> String {
+    if bytes >= 0 {
+        format!("rdx+{}", bytes)
+    } else {
+        format!("rdx-{}", -bytes)
+    }
+}
+
+/// Emit `add`/`sub [mem], N` for a folded `Op::Add` delta, sized to `width`
+fn emit_add(out: &mut String, mem: &str, delta: i64, width: &Width) {
+    let delta = wrap_to_width(delta, width);
+    let half = (width.max + 1) / 2;
+    if delta == 0 {
+        // no-op fold, e.g. a `+` and `-` that canceled out
+    } else if delta <= half {
+        out.push_str(&format!("    add {}, {}\n", mem, delta));
+    } else {
+        out.push_str(&format!("    sub {}, {}\n", mem, width.max + 1 - delta));
+    }
+}
+
+/// Reduce a wide delta/factor to the unsigned value it's equivalent to mod `width`'s range
+fn wrap_to_width(v: i64, width: &Width) -> i64 {
+    v.rem_euclid(width.max + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tape::CellWidth;
+    use crate::{compile, tokenize};
+
+    fn generate_for(source: &str, cell_width: CellWidth) -> String {
+        let ops = compile(&tokenize(source).unwrap());
+        let config = TapeConfig { cell_width, bounds: Bounds::Fixed(10), ..TapeConfig::default() };
+        generate(&ops, &config).unwrap()
+    }
+
+    #[test]
+    fn multiply_loop_loads_an_8_bit_cell_with_movzx() {
+        let out = generate_for("++[->+<]", CellWidth::U8);
+        assert!(out.contains("movzx eax, byte [rdx]"));
+    }
+
+    #[test]
+    fn multiply_loop_loads_a_16_bit_cell_with_movzx() {
+        let out = generate_for("++[->+<]", CellWidth::U16);
+        assert!(out.contains("movzx eax, word [rdx]"));
+    }
+
+    #[test]
+    fn multiply_loop_loads_a_32_bit_cell_with_plain_mov() {
+        // movzx into eax from a 32-bit source isn't a valid encoding;
+        // a plain `mov` already zero-extends the rest of rax
+        let out = generate_for("++[->+<]", CellWidth::U32);
+        assert!(out.contains("mov eax, dword [rdx]"));
+        assert!(!out.contains("movzx eax, dword [rdx]"));
+    }
+
+    #[test]
+    fn a_move_is_bounds_checked_against_the_fixed_tape_before_it_happens() {
+        let out = generate_for(">", CellWidth::U8);
+        assert!(out.contains("cmp rax, r14"));
+        assert!(out.contains("jl oob_trap"));
+        assert!(out.contains("cmp rax, r15"));
+        assert!(out.contains("jg oob_trap"));
+        // the bounds check must come before the pointer actually moves
+        assert!(out.find("jg oob_trap").unwrap() < out.find("add rdx, 1").unwrap());
+    }
+
+    #[test]
+    fn a_multiply_loops_write_is_bounds_checked_even_though_the_pointer_never_moves() {
+        let out = generate_for("++[->+<]", CellWidth::U8);
+        assert!(out.find("jg oob_trap").unwrap() < out.find("movzx eax, byte [rdx]").unwrap());
+    }
+
+    #[test]
+    fn the_oob_trap_reports_the_same_message_the_interpreter_would() {
+        let out = generate_for(">", CellWidth::U8);
+        assert!(out.contains("\noob_trap:"));
+        assert!(out.contains("Data pointer moved outside the fixed-size tape"));
+    }
+}
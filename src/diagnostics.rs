@@ -0,0 +1,60 @@
+//! This file is part of brainrust and is copyright Peter Beard
+//! Licensed under the GPL v3, see LICENSE for details
+//!
+//! Source-position tracking and caret-style error rendering. Every token
+//! remembers where it came from so errors can point at the offending
+//! character in the original source instead of a raw token index.
+
+/// A location within the original source text
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// The position of the first character in a source file
+    pub fn start() -> Position {
+        Position { offset: 0, line: 1, column: 1 }
+    }
+
+    /// Advance past `ch`, updating line/column as needed
+    pub fn advance(&mut self, ch: char) {
+        self.offset += ch.len_utf8();
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+/// An error tied to a position in the source text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Error {
+    pub message: String,
+    pub pos: Position,
+}
+
+impl Error {
+    pub fn new(message: impl Into<String>, pos: Position) -> Error {
+        Error { message: message.into(), pos }
+    }
+
+    /// Render this error against `source`, with a caret under the offending column
+    pub fn render(&self, source: &str) -> String {
+        let line_text = source.lines().nth(self.pos.line - 1).unwrap_or("");
+        let gutter = format!("{}", self.pos.line);
+        let caret = " ".repeat(self.pos.column.saturating_sub(1));
+        format!(
+            "error: {}\n{pad} |\n{line} | {text}\n{pad} | {caret}^",
+            self.message,
+            pad = " ".repeat(gutter.len()),
+            line = gutter,
+            text = line_text,
+            caret = caret,
+        )
+    }
+}
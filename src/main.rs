@@ -6,8 +6,16 @@ use std::io;
 use std::io::Read;
 use std::env;
 
+mod asm;
+mod diagnostics;
+mod repl;
+mod tape;
+
+use diagnostics::{Error, Position};
+use tape::{Bounds, CellWidth, EofBehavior, Overflow, Tape, TapeConfig};
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-enum Token {
+enum TokenKind {
     RAngle,
     LAngle,
     Plus,
@@ -18,6 +26,13 @@ enum Token {
     RBracket(usize),
 }
 
+/// A token together with the position it came from in the source text
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Token {
+    kind: TokenKind,
+    pos: Position,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum TokenizerState {
     Token,
@@ -25,12 +40,34 @@ enum TokenizerState {
     LeadingWhitespace,
 }
 
+/// A single optimized operation making up a compiled program
+///
+/// Unlike `Token`, runs of `+`/`-` and `>`/`<` are folded into a single
+/// `Add`/`Move`, and a few common loop idioms are recognized and replaced
+/// with an operation that performs the whole loop in one step
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Op {
+    Add(i64),
+    /// A run of `>`/`<` folded into one op. `delta` is the net movement;
+    /// `min`/`max` are the furthest left/right the pointer reached while
+    /// getting there (relative to the position where the run started), so
+    /// bounds checks can catch a transient violation even when `delta`
+    /// alone would look safe (e.g. `<>` nets to zero but dips to -1)
+    Move { delta: isize, min: isize, max: isize, pos: Position },
+    Out,
+    In { pos: Position },
+    Clear,
+    AddMul { offset: isize, factor: i64, pos: Position },
+    JumpIfZero(usize),
+    JumpIfNonZero(usize),
+}
+
 /// Load a program from a file
 fn load_from_file(filename: &Path) -> Option<String> {
     let fh = File::open(filename);
     if let Ok(mut f) = fh {
         let mut contents = String::new();
-        
+
         if let Ok(_) = f.read_to_string(&mut contents) {
             Some(contents)
         } else {
@@ -41,9 +78,9 @@ fn load_from_file(filename: &Path) -> Option<String> {
     }
 }
 
-/// Parse a char to a Token
-fn char_to_token(ch: &char) -> Option<Token> {
-    use Token::*;
+/// Parse a char to a TokenKind
+fn char_to_token(ch: &char) -> Option<TokenKind> {
+    use TokenKind::*;
     match *ch {
         '>' => Some(RAngle),
         '<' => Some(LAngle),
@@ -64,22 +101,30 @@ fn char_to_token(ch: &char) -> Option<Token> {
 /// Tokenize a string
 ///
 /// Tokenization basically just conists of ignoring characters outside the BF alphabet
-fn tokenize(input: &str) -> Vec<Token> {
-    use Token::*;
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    use TokenKind::*;
 
     let mut tokens: Vec<Token> = Vec::with_capacity(input.len());
     let mut state = TokenizerState::LeadingWhitespace;
+    let mut pos = Position::start();
     for character in input.chars() {
         match state {
             TokenizerState::LeadingWhitespace => {
-                if let Some(t) = char_to_token(&character) {
+                if let Some(kind) = char_to_token(&character) {
                     state = TokenizerState::Token;
-                    tokens.push(t);
+                    tokens.push(Token { kind, pos });
                 }
             },
             TokenizerState::Token => {
-                if let Some(t) = char_to_token(&character) {
-                    tokens.push(t);
+                if let Some(kind) = char_to_token(&character) {
+                    tokens.push(Token { kind, pos });
+                } else if character == '\n' || character == '\r' {
+                    // The newline ending this run already satisfies
+                    // TrailingWhitespace's own reset check, so go straight
+                    // back to LeadingWhitespace instead of waiting for a
+                    // second newline to notice - otherwise the first real
+                    // token on the next line is silently swallowed
+                    state = TokenizerState::LeadingWhitespace;
                 } else {
                     state = TokenizerState::TrailingWhitespace;
                 }
@@ -90,124 +135,496 @@ fn tokenize(input: &str) -> Vec<Token> {
                 }
             },
         }
+        pos.advance(character);
     }
 
     // Match up brackets
     for (i, t) in tokens.clone().into_iter().enumerate() {
-        match t {
+        match t.kind {
             LBracket(_) => {
                 let mut depth = 1;
                 let mut p = i+1;
                 while depth > 0 && p < tokens.len() {
-                    if let RBracket(_) = tokens[p] {
+                    if let RBracket(_) = tokens[p].kind {
                         depth -= 1;
-                    } else if let LBracket(_) = tokens[p] {
+                    } else if let LBracket(_) = tokens[p].kind {
                         depth += 1;
                     }
                     p += 1;
                 }
-                if let RBracket(_) = tokens[p-1] {
-                    tokens[i] = LBracket(p);
+                // `p < tokens.len()` can end the scan just as easily as
+                // `depth` reaching 0 - e.g. `[[]` runs out of tokens with an
+                // unclosed nesting level still open - so check depth itself
+                // rather than trusting whatever token the scan happened to
+                // stop on
+                if depth == 0 {
+                    tokens[i].kind = LBracket(p);
                 } else {
-                    panic!("Unmatched [ at {}", i);
+                    return Err(Error::new("Unmatched [", t.pos));
                 }
             },
             RBracket(_) => {
-                let mut depth = -1;
-                let mut p = i-1;
-                while depth < 0 && p > 0 {
-                    if let RBracket(_) = tokens[p] {
-                        depth -= 1;
-                    } else if let LBracket(_) = tokens[p] {
+                if i == 0 {
+                    // No token before this `]` for the backward scan to even
+                    // start from
+                    return Err(Error::new("Unmatched ]", t.pos));
+                }
+                let mut depth = 1;
+                let mut p = i;
+                while depth > 0 && p > 0 {
+                    p -= 1;
+                    if let RBracket(_) = tokens[p].kind {
                         depth += 1;
+                    } else if let LBracket(_) = tokens[p].kind {
+                        depth -= 1;
                     }
-                    p -= 1;
                 }
-                if let LBracket(_) = tokens[p+1] {
-                    tokens[i] = RBracket(p);
-                } else if p == 0 {
-                    tokens[i] = RBracket(1);
+                if depth == 0 {
+                    tokens[i].kind = RBracket(p);
                 } else {
-                    panic!("Unmatched ] at {}", i);
+                    return Err(Error::new("Unmatched ]", t.pos));
                 }
             },
             _ => {},
         }
     }
-    tokens
+    Ok(tokens)
 }
 
-/// Run a brainfuck program
+/// Compile a bracket-matched token stream into a compact list of `Op`s
 ///
-/// A program is just an array of tokens since the language doesn't really
-/// require an AST to be generated
-fn run_program(program: &[Token]) {
-    use Token::*;
-
-    let mut data: Vec<u8> = Vec::new();
-    let mut data_pointer: usize = 0;
-    let mut instr_pointer: usize = 0;
-
-    while instr_pointer < program.len() {
-        // Pretend we have an infinite tape
-        if data_pointer >= data.len() {
-            data.push(0);
-        }
+/// This folds runs of `+`/`-` and `>`/`<` into single `Add`/`Move` ops and
+/// recognizes a few common loop idioms (see `recognize_idiom`) so the
+/// interpreter does much less work per loop iteration
+fn compile(tokens: &[Token]) -> Vec<Op> {
+    let mut ops = Vec::with_capacity(tokens.len());
+    compile_block(tokens, 0, tokens.len(), &mut ops);
+    ops
+}
 
-        match program[instr_pointer] {
-            RAngle => {
-                data_pointer += 1;
-            },
-            LAngle => {
-                if data_pointer == 0 {
-                    panic!("Cannot decrement zero data pointer");
+/// Compile `tokens[start..end]`, appending the resulting ops to `ops`
+fn compile_block(tokens: &[Token], start: usize, end: usize, ops: &mut Vec<Op>) {
+    use TokenKind::*;
+
+    let mut i = start;
+    while i < end {
+        match tokens[i].kind {
+            Plus | Minus => {
+                let mut delta: i64 = 0;
+                while i < end {
+                    match tokens[i].kind {
+                        Plus => delta += 1,
+                        Minus => delta -= 1,
+                        _ => break,
+                    }
+                    i += 1;
                 }
-                data_pointer -= 1;
+                ops.push(Op::Add(delta));
             },
-            Plus => {
-                data[data_pointer] = data[data_pointer].wrapping_add(1);
-            },
-            Minus => {
-                data[data_pointer] = data[data_pointer].wrapping_sub(1);
+            RAngle | LAngle => {
+                let pos = tokens[i].pos;
+                let mut delta: isize = 0;
+                let mut min: isize = 0;
+                let mut max: isize = 0;
+                while i < end {
+                    match tokens[i].kind {
+                        RAngle => delta += 1,
+                        LAngle => delta -= 1,
+                        _ => break,
+                    }
+                    min = min.min(delta);
+                    max = max.max(delta);
+                    i += 1;
+                }
+                ops.push(Op::Move { delta, min, max, pos });
             },
             Period => {
-                print!("{}", data[data_pointer] as char);
+                ops.push(Op::Out);
+                i += 1;
             },
             Comma => {
-                let mut buf: [u8; 1] = [0];
-                let count = io::stdin().read_exact(&mut buf);
-                if let Ok(_) = count {
-                    data[data_pointer] = buf[0];
-                } else {
-                    panic!("Error reading from STDIN: {:?}", count);
-                }
+                ops.push(Op::In { pos: tokens[i].pos });
+                i += 1;
             },
-            LBracket(pointer) => {
-                if data[data_pointer] == 0 {
-                    instr_pointer = pointer;
+            LBracket(close) => {
+                // `close` (set by tokenize) is one past the matching RBracket's index
+                let rbracket = close - 1;
+                match recognize_idiom(&tokens[i+1..rbracket]) {
+                    Some(idiom) => ops.extend(idiom),
+                    None => {
+                        let jump_if_zero = ops.len();
+                        ops.push(Op::JumpIfZero(0));
+                        compile_block(tokens, i+1, rbracket, ops);
+                        ops.push(Op::JumpIfNonZero(jump_if_zero));
+                        let end_index = ops.len() - 1;
+                        ops[jump_if_zero] = Op::JumpIfZero(end_index);
+                    },
                 }
+                i = close;
             },
-            RBracket(pointer) => {
-                if data[data_pointer] != 0 {
-                    instr_pointer = pointer;
+            RBracket(_) => unreachable!("compile_block never descends into an RBracket directly"),
+        }
+    }
+}
+
+/// Recognize a loop body as one of a few common BF idioms
+///
+/// Returns the ops that implement the idiom in one step, or `None` if the
+/// body doesn't match any known idiom and should be compiled as a normal loop
+fn recognize_idiom(body: &[Token]) -> Option<Vec<Op>> {
+    use TokenKind::*;
+
+    // `[-]` / `[+]`: zero the current cell
+    if body.len() == 1 && matches!(body[0].kind, Plus | Minus) {
+        return Some(vec![Op::Clear]);
+    }
+
+    // Multiply/copy loops like `[->+>++<<]`: walk the body tracking the net
+    // pointer offset and the net delta applied at each offset visited. To
+    // qualify, the pointer must end up back where it started, the current
+    // cell must be decremented by exactly one, and every other cell touched
+    // must only be incremented.
+    let mut offset: isize = 0;
+    let mut deltas: Vec<(isize, i32)> = Vec::new();
+    let mut i = 0;
+    while i < body.len() {
+        match body[i].kind {
+            Plus | Minus => {
+                let mut delta = 0;
+                while i < body.len() {
+                    match body[i].kind {
+                        Plus => delta += 1,
+                        Minus => delta -= 1,
+                        _ => break,
+                    }
+                    i += 1;
+                }
+                match deltas.iter_mut().find(|(o, _)| *o == offset) {
+                    Some(entry) => entry.1 += delta,
+                    None => deltas.push((offset, delta)),
                 }
             },
+            RAngle => {
+                offset += 1;
+                i += 1;
+            },
+            LAngle => {
+                offset -= 1;
+                i += 1;
+            },
+            // Loops, I/O, or anything else inside the body: not a simple idiom
+            Period | Comma | LBracket(_) | RBracket(_) => return None,
+        }
+    }
+    if offset != 0 {
+        return None;
+    }
+
+    let current = deltas.iter().find(|(o, _)| *o == 0).map(|(_, d)| *d);
+    if current != Some(-1) {
+        return None;
+    }
+    if deltas.iter().any(|(o, d)| *o != 0 && *d <= 0) {
+        return None;
+    }
+
+    let pos = body[0].pos;
+    let mut ops: Vec<Op> = deltas.iter()
+        .filter(|(o, _)| *o != 0)
+        .map(|(o, d)| Op::AddMul { offset: *o, factor: *d as i64, pos })
+        .collect();
+    ops.push(Op::Clear);
+    Some(ops)
+}
+
+/// Interpreter state: just the tape, really
+///
+/// Kept separate from `run_program` so a REPL can hold onto an `Interpreter`
+/// and run successive chunks of compiled code against the same tape
+pub(crate) struct Interpreter {
+    tape: Tape,
+}
+
+impl Interpreter {
+    pub(crate) fn new(config: TapeConfig) -> Interpreter {
+        Interpreter { tape: Tape::new(config) }
+    }
+
+    /// Run a compiled program against the current tape state
+    pub(crate) fn run(&mut self, program: &[Op]) -> Result<(), Error> {
+        let mut instr_pointer: usize = 0;
+
+        while instr_pointer < program.len() {
+            match program[instr_pointer] {
+                Op::Add(delta) => {
+                    self.tape.add(delta);
+                },
+                Op::Move { delta, min, max, pos } => {
+                    self.tape.move_by(delta, min, max).map_err(|msg| Error::new(msg, pos))?;
+                },
+                Op::Out => {
+                    print!("{}", (self.tape.get() as u8) as char);
+                },
+                Op::In { pos } => {
+                    let mut buf: [u8; 1] = [0];
+                    let result = io::stdin().read_exact(&mut buf).map(|_| buf[0]);
+                    self.tape.apply_input(result).map_err(|msg| Error::new(msg, pos))?;
+                },
+                Op::Clear => {
+                    self.tape.set(0);
+                },
+                Op::AddMul { offset, factor, pos } => {
+                    let delta = self.tape.get() * factor;
+                    self.tape.add_at(offset, delta).map_err(|msg| Error::new(msg, pos))?;
+                },
+                Op::JumpIfZero(target) => {
+                    if self.tape.get() == 0 {
+                        instr_pointer = target;
+                    }
+                },
+                Op::JumpIfNonZero(target) => {
+                    if self.tape.get() != 0 {
+                        instr_pointer = target;
+                    }
+                },
+            }
+            instr_pointer += 1;
         }
-        instr_pointer += 1;
+        Ok(())
     }
+
+    /// A one-line dump of the nonzero cells around the data pointer, for the REPL's `:tape` command
+    pub(crate) fn dump_tape(&self) -> String {
+        let pointer = self.tape.position();
+        let cells: Vec<String> = self.tape.nonzero_near(8).into_iter()
+            .map(|(i, v)| {
+                if i == pointer {
+                    format!("[{}]={}*", i, v)
+                } else {
+                    format!("[{}]={}", i, v)
+                }
+            })
+            .collect();
+
+        if cells.is_empty() {
+            format!("(all zero, pointer at {})", pointer)
+        } else {
+            cells.join(" ")
+        }
+    }
+}
+
+/// Run a compiled brainfuck program against a fresh tape
+fn run_program(program: &[Op], config: TapeConfig) -> Result<(), Error> {
+    Interpreter::new(config).run(program)
+}
+
+/// What to do with a compiled program
+enum EmitMode {
+    /// Interpret it directly
+    Run,
+    /// Print NASM-syntax x86-64 assembly for it
+    Asm,
+}
+
+/// Parse CLI args (excluding argv[0]) into a filename, an emit mode, and the tape's configuration
+fn parse_args(args: &[String]) -> (Option<String>, EmitMode, TapeConfig) {
+    let mut filename = None;
+    let mut emit = EmitMode::Run;
+    let mut config = TapeConfig::default();
+    for arg in args {
+        if let Some(value) = arg.strip_prefix("--cell=") {
+            config.cell_width = match value {
+                "8" => CellWidth::U8,
+                "16" => CellWidth::U16,
+                "32" => CellWidth::U32,
+                _ => panic!("Unknown --cell value '{}' (expected 8, 16, or 32)", value),
+            };
+        } else if let Some(value) = arg.strip_prefix("--overflow=") {
+            config.overflow = match value {
+                "wrap" => Overflow::Wrap,
+                "saturate" => Overflow::Saturate,
+                _ => panic!("Unknown --overflow value '{}' (expected wrap or saturate)", value),
+            };
+        } else if let Some(value) = arg.strip_prefix("--eof=") {
+            config.eof = match value {
+                "zero" => EofBehavior::Zero,
+                "minus-one" => EofBehavior::MinusOne,
+                "unchanged" => EofBehavior::Unchanged,
+                _ => panic!("Unknown --eof value '{}' (expected zero, minus-one, or unchanged)", value),
+            };
+        } else if let Some(value) = arg.strip_prefix("--tape=") {
+            config.bounds = match value {
+                "unbounded" => Bounds::Unbounded,
+                n => Bounds::Fixed(n.parse().unwrap_or_else(|_| panic!("Unknown --tape value '{}' (expected unbounded or a cell count)", n))),
+            };
+        } else if arg == "--emit=asm" {
+            emit = EmitMode::Asm;
+        } else {
+            filename = Some(arg.clone());
+        }
+    }
+    (filename, emit, config)
+}
+
+/// Tokenize, compile, and either run or emit assembly for `source`
+fn run(source: &str, emit: EmitMode, config: TapeConfig) -> Result<(), Error> {
+    let tokens = tokenize(source)?;
+    let ops = compile(&tokens);
+    match emit {
+        EmitMode::Run => run_program(&ops, config)?,
+        EmitMode::Asm => {
+            let text = asm::generate(&ops, &config).map_err(|msg| Error::new(msg, Position::start()))?;
+            print!("{}", text);
+        },
+    }
+    Ok(())
 }
 
 /// Entry point
 fn main() {
-    let program = if let Some(fname) = env::args().nth(1) {
-        load_from_file(Path::new(&fname))
-    } else {
-        panic!("No filename provided.");
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (filename, emit, config) = parse_args(&args);
+    let fname = match filename {
+        Some(fname) => fname,
+        None => return repl::run(config),
     };
+    let program = load_from_file(Path::new(&fname));
     if let Some(p) = program {
-        let tokens = tokenize(&p);
-        run_program(&tokens);
+        if let Err(err) = run(&p, emit, config) {
+            eprintln!("{}", err.render(&p));
+            std::process::exit(1);
+        }
     } else {
         panic!("Failed to load file");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile_source(source: &str) -> Vec<Op> {
+        compile(&tokenize(source).unwrap())
+    }
+
+    #[test]
+    fn fold_tracks_overshoot_in_mixed_direction_runs() {
+        // `<>` nets to delta 0 but dips to -1 before coming back
+        let ops = compile_source("<>");
+        assert_eq!(ops, vec![Op::Move { delta: 0, min: -1, max: 0, pos: Position::start() }]);
+
+        // `>><` nets to +1 but reaches +2 along the way
+        let ops = compile_source(">><");
+        assert_eq!(ops, vec![Op::Move { delta: 1, min: 0, max: 2, pos: Position::start() }]);
+    }
+
+    #[test]
+    fn fold_pure_runs_have_min_max_at_the_endpoints() {
+        let ops = compile_source(">>>");
+        assert_eq!(ops, vec![Op::Move { delta: 3, min: 0, max: 3, pos: Position::start() }]);
+
+        let ops = compile_source("<<<");
+        assert_eq!(ops, vec![Op::Move { delta: -3, min: -3, max: 0, pos: Position::start() }]);
+    }
+
+    #[test]
+    fn recognize_idiom_clears_cell() {
+        let tokens = tokenize("[-]").unwrap();
+        let idiom = recognize_idiom(&tokens[1..tokens.len() - 1]);
+        assert_eq!(idiom, Some(vec![Op::Clear]));
+    }
+
+    #[test]
+    fn recognize_idiom_folds_multiply_loop() {
+        let tokens = tokenize("[->+>++<<]").unwrap();
+        let ops = recognize_idiom(&tokens[1..tokens.len() - 1]).unwrap();
+        let pos = tokens[1].pos;
+        assert_eq!(ops, vec![
+            Op::AddMul { offset: 1, factor: 1, pos },
+            Op::AddMul { offset: 2, factor: 2, pos },
+            Op::Clear,
+        ]);
+    }
+
+    #[test]
+    fn recognize_idiom_rejects_loops_with_io_or_nesting() {
+        let tokens = tokenize("[->+<.]").unwrap();
+        assert_eq!(recognize_idiom(&tokens[1..tokens.len() - 1]), None);
+
+        let tokens = tokenize("[->+<[-]]").unwrap();
+        assert_eq!(recognize_idiom(&tokens[1..tokens.len() - 1]), None);
+    }
+
+    #[test]
+    fn recognize_idiom_rejects_loops_that_dont_return_to_start() {
+        let tokens = tokenize("[->+>]").unwrap();
+        assert_eq!(recognize_idiom(&tokens[1..tokens.len() - 1]), None);
+    }
+
+    #[test]
+    fn decrement_below_the_tape_start_is_reported_as_an_error() {
+        // The pointer starts at cell 0; `<` must fail even though the very
+        // next `>` would bring the net movement back to zero
+        let ops = compile_source("<>");
+        let config = TapeConfig { bounds: Bounds::Fixed(1), ..TapeConfig::default() };
+        assert!(run_program(&ops, config).is_err());
+    }
+
+    #[test]
+    fn a_token_immediately_followed_by_a_newline_does_not_swallow_the_next_line() {
+        // A single `\n` right after a token used to only flip the FSM into
+        // TrailingWhitespace, not all the way back to LeadingWhitespace, so
+        // the first real token on the following line was silently dropped
+        let tokens = tokenize("+\n+\n+\n.").unwrap();
+        assert_eq!(tokens.len(), 4);
+
+        let ops = compile_source("+\n+\n+\n.");
+        assert_eq!(ops, vec![Op::Add(3), Op::Out]);
+    }
+
+    #[test]
+    fn a_multiply_loop_split_across_lines_still_folds_into_one_op() {
+        // Representative of how a real program like mandelbrot.b is laid
+        // out: the loop body spans several lines, so this only folds to a
+        // single AddMul/Clear now that tokenize() no longer drops tokens
+        // at a line boundary
+        let source = "+++[\n>+\n<-]";
+        let tokens = tokenize(source).unwrap();
+        assert_eq!(tokens.len(), 9, "every token on every line must survive tokenization");
+
+        let ops = compile_source(source);
+        let pos = tokens[4].pos;
+        assert_eq!(ops, vec![Op::Add(3), Op::AddMul { offset: 1, factor: 1, pos }, Op::Clear]);
+    }
+
+    #[test]
+    fn position_tracking_stays_correct_for_tokens_on_separate_lines() {
+        // The position-tracking this request added is only as trustworthy
+        // as the token stream it's attached to; before the line-boundary
+        // tokenizer fix, the tokens on lines 2 and 4 would have been
+        // dropped entirely instead of merely mis-positioned
+        let tokens = tokenize("+\n+\n+\n.").unwrap();
+        let lines: Vec<usize> = tokens.iter().map(|t| t.pos.line).collect();
+        assert_eq!(lines, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn a_stray_close_bracket_is_reported_as_an_error_instead_of_panicking() {
+        // Every one of these used to either underflow `p = i - 1` at `i == 0`
+        // or fall into the `p == 0` fallback that mislabeled the token as
+        // matched, which then hit `unreachable!()` in compile_block
+        for source in ["]", "+]", "]]", ".]", ",]", ">]", "[]]"] {
+            assert!(tokenize(source).is_err(), "expected {:?} to fail to tokenize", source);
+        }
+    }
+
+    #[test]
+    fn an_unterminated_nested_loop_is_reported_as_an_error() {
+        // The forward scan used to accept whatever token it happened to
+        // stop on as a match, so running out of tokens with depth still
+        // open (an unclosed inner loop) was silently treated as balanced
+        assert!(tokenize("[[]").is_err());
+        assert!(tokenize("[[]]").is_ok());
+    }
+}
@@ -0,0 +1,125 @@
+/// This file is part of brainrust and is copyright Peter Beard
+/// Licensed under the GPL v3, see LICENSE for details
+///
+/// An interactive REPL: reads Brainfuck a line at a time, persisting the
+/// tape and data pointer between inputs so state can be built up
+/// incrementally instead of starting over with every line
+use std::io::{self, BufRead, Write};
+
+use crate::tape::TapeConfig;
+use crate::{compile, tokenize, Interpreter};
+
+/// Run the REPL against stdin/stdout until EOF or `:quit`
+pub(crate) fn run(config: TapeConfig) {
+    let stdin = io::stdin();
+    let mut interp = Interpreter::new(config);
+    let mut buffer = String::new();
+
+    prompt(&buffer);
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+
+        if buffer.is_empty() {
+            match line.trim() {
+                ":quit" => break,
+                ":reset" => {
+                    interp = Interpreter::new(config);
+                    println!("tape reset");
+                    prompt(&buffer);
+                    continue;
+                },
+                ":tape" => {
+                    println!("{}", interp.dump_tape());
+                    prompt(&buffer);
+                    continue;
+                },
+                _ => {},
+            }
+        }
+
+        buffer.push_str(&line);
+        buffer.push('\n');
+
+        if !brackets_balanced(&buffer) {
+            prompt(&buffer);
+            continue;
+        }
+
+        let source = std::mem::take(&mut buffer);
+        match tokenize(&source) {
+            Ok(tokens) => {
+                let ops = compile(&tokens);
+                if let Err(err) = interp.run(&ops) {
+                    println!("{}", err.render(&source));
+                }
+            },
+            Err(err) => println!("{}", err.render(&source)),
+        }
+        prompt(&buffer);
+    }
+}
+
+/// Print the prompt for the next line, `...` while a loop is still open
+fn prompt(buffer: &str) {
+    if buffer.is_empty() {
+        print!("brainrust> ");
+    } else {
+        print!("...        ");
+    }
+    io::stdout().flush().ok();
+}
+
+/// Whether `[`/`]` are balanced, i.e. whether `input` has no partially-entered loop
+fn brackets_balanced(input: &str) -> bool {
+    let mut depth = 0i32;
+    for ch in input.chars() {
+        match ch {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => {},
+        }
+        // A stray `]` can't become balanced by typing more input; let
+        // tokenize() report it as a proper error instead of buffering forever
+        if depth < 0 {
+            return true;
+        }
+    }
+    depth == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Op;
+
+    #[test]
+    fn brackets_balanced_waits_for_a_partially_entered_loop_to_close() {
+        assert!(!brackets_balanced("+++[\n"));
+        assert!(!brackets_balanced("+++[\n>+\n"));
+        assert!(brackets_balanced("+++[\n>+\n<-]\n"));
+    }
+
+    #[test]
+    fn a_loop_entered_one_line_at_a_time_compiles_to_the_same_program_as_one_line() {
+        // Mirrors `run`'s own buffer-then-tokenize loop: each line is
+        // appended with its newline until the brackets balance, then the
+        // whole buffer is tokenized and compiled in one shot
+        let mut buffer = String::new();
+        for line in ["+++[", ">+", "<-]"] {
+            buffer.push_str(line);
+            buffer.push('\n');
+            if !brackets_balanced(&buffer) {
+                continue;
+            }
+        }
+        assert!(brackets_balanced(&buffer));
+
+        let multiline_ops = compile(&tokenize(&buffer).unwrap());
+        let oneline_ops = compile(&tokenize("+++[>+<-]").unwrap());
+        assert_eq!(multiline_ops.len(), oneline_ops.len());
+        assert!(matches!(multiline_ops.as_slice(), [Op::Add(3), Op::AddMul { .. }, Op::Clear]));
+    }
+}
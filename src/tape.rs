@@ -0,0 +1,239 @@
+/// This file is part of brainrust and is copyright Peter Beard
+/// Licensed under the GPL v3, see LICENSE for details
+///
+/// Configurable tape semantics. Different Brainfuck dialects disagree on
+/// cell width, what happens on overflow, what `,` does at end-of-input,
+/// and whether the tape is a fixed size or grows as needed - `TapeConfig`
+/// picks among them and `Tape` implements whichever combination is chosen.
+use std::io;
+
+/// Width of a single tape cell
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CellWidth {
+    U8,
+    U16,
+    U32,
+}
+
+impl CellWidth {
+    fn max_value(self) -> i64 {
+        match self {
+            CellWidth::U8 => u8::MAX as i64,
+            CellWidth::U16 => u16::MAX as i64,
+            CellWidth::U32 => u32::MAX as i64,
+        }
+    }
+}
+
+/// What happens when a cell's value would carry past its width
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Overflow {
+    Wrap,
+    Saturate,
+}
+
+/// What a cell becomes when `,` is executed at end-of-input
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum EofBehavior {
+    Zero,
+    MinusOne,
+    Unchanged,
+}
+
+/// Whether the tape is a fixed size or grows in both directions as needed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Bounds {
+    Fixed(usize),
+    Unbounded,
+}
+
+/// How a `Tape` should behave; see the individual types for the choices
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TapeConfig {
+    pub cell_width: CellWidth,
+    pub overflow: Overflow,
+    pub eof: EofBehavior,
+    pub bounds: Bounds,
+}
+
+impl Default for TapeConfig {
+    fn default() -> TapeConfig {
+        TapeConfig {
+            cell_width: CellWidth::U8,
+            overflow: Overflow::Wrap,
+            eof: EofBehavior::Zero,
+            bounds: Bounds::Unbounded,
+        }
+    }
+}
+
+/// A brainfuck tape: a line of cells with a data pointer into them
+///
+/// `Unbounded` tapes grow in both directions as the pointer moves past
+/// either end; `origin` is the index into `cells` that represents data
+/// pointer zero, since `cells` itself can never shrink on the left
+pub(crate) struct Tape {
+    config: TapeConfig,
+    cells: Vec<i64>,
+    origin: usize,
+    pointer: usize,
+}
+
+impl Tape {
+    pub(crate) fn new(config: TapeConfig) -> Tape {
+        let len = match config.bounds {
+            Bounds::Fixed(n) => n.max(1),
+            Bounds::Unbounded => 1,
+        };
+        Tape { config, cells: vec![0; len], origin: 0, pointer: 0 }
+    }
+
+    /// The current cell's value
+    pub(crate) fn get(&self) -> i64 {
+        self.cells[self.pointer]
+    }
+
+    /// Set the current cell, applying the configured overflow behavior
+    pub(crate) fn set(&mut self, value: i64) {
+        self.cells[self.pointer] = self.clamp(value);
+    }
+
+    /// Add `delta` to the current cell
+    pub(crate) fn add(&mut self, delta: i64) {
+        self.set(self.get() + delta);
+    }
+
+    /// Add `delta` to the cell at `offset` from the pointer, leaving the
+    /// pointer where it started
+    pub(crate) fn add_at(&mut self, offset: isize, delta: i64) -> Result<(), String> {
+        let lo = offset.min(0);
+        let hi = offset.max(0);
+        self.move_by(offset, lo, hi)?;
+        self.add(delta);
+        self.move_by(-offset, -hi, -lo)
+    }
+
+    /// Move the pointer by `delta`, growing or rejecting as the configured
+    /// bounds dictate. `min`/`max` are the furthest left/right of the
+    /// pointer's starting position this move passes through (not just its
+    /// endpoint), so a fixed-size tape rejects a transient excursion even
+    /// if the net `delta` lands back in bounds
+    pub(crate) fn move_by(&mut self, delta: isize, min: isize, max: isize) -> Result<(), String> {
+        match self.config.bounds {
+            Bounds::Fixed(_) => {
+                let lo = self.pointer as isize + min;
+                let hi = self.pointer as isize + max;
+                if lo < 0 || hi as usize >= self.cells.len() {
+                    return Err("Data pointer moved outside the fixed-size tape".to_string());
+                }
+                self.pointer = (self.pointer as isize + delta) as usize;
+            },
+            Bounds::Unbounded => {
+                let lo = self.pointer as isize + min;
+                let mut hi = self.pointer as isize + max;
+                let mut new_index = self.pointer as isize + delta;
+                if lo < 0 {
+                    let grow = (-lo) as usize;
+                    let mut grown = vec![0; grow];
+                    grown.extend_from_slice(&self.cells);
+                    self.cells = grown;
+                    self.origin += grow;
+                    hi += grow as isize;
+                    new_index += grow as isize;
+                }
+                while hi as usize >= self.cells.len() {
+                    self.cells.push(0);
+                }
+                self.pointer = new_index as usize;
+            },
+        }
+        Ok(())
+    }
+
+    /// The pointer's position relative to where the tape started (cell 0)
+    pub(crate) fn position(&self) -> isize {
+        self.pointer as isize - self.origin as isize
+    }
+
+    /// Apply a `,` read: `input` is the result of reading one byte from stdin
+    pub(crate) fn apply_input(&mut self, input: io::Result<u8>) -> Result<(), String> {
+        match input {
+            Ok(byte) => {
+                self.set(byte as i64);
+                Ok(())
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                match self.config.eof {
+                    EofBehavior::Zero => self.set(0),
+                    // All bits set, i.e. this cell width's max value - not
+                    // the literal -1, which Overflow::Saturate's clamp(0, max)
+                    // would otherwise flatten to 0 instead of the max value
+                    EofBehavior::MinusOne => self.set(self.config.cell_width.max_value()),
+                    EofBehavior::Unchanged => {},
+                }
+                Ok(())
+            },
+            Err(e) => Err(format!("Error reading from STDIN: {:?}", e)),
+        }
+    }
+
+    /// The nonzero cells within `window` of the pointer (plus the pointer's
+    /// own cell even if zero), as `(position, value)` pairs
+    pub(crate) fn nonzero_near(&self, window: usize) -> Vec<(isize, i64)> {
+        let start = self.pointer.saturating_sub(window);
+        let end = (self.pointer + window + 1).min(self.cells.len());
+        (start..end)
+            .filter(|&i| self.cells[i] != 0 || i == self.pointer)
+            .map(|i| (i as isize - self.origin as isize, self.cells[i]))
+            .collect()
+    }
+
+    fn clamp(&self, value: i64) -> i64 {
+        let max = self.config.cell_width.max_value();
+        match self.config.overflow {
+            Overflow::Wrap => value.rem_euclid(max + 1),
+            Overflow::Saturate => value.clamp(0, max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_bounds_reject_a_transient_excursion_even_if_the_endpoint_is_in_range() {
+        let mut tape = Tape::new(TapeConfig { bounds: Bounds::Fixed(2), ..TapeConfig::default() });
+        // From cell 0: go to cell 1, then cell 2 (out of range for size 2), then back to 1
+        assert!(tape.move_by(1, 0, 2).is_err());
+    }
+
+    #[test]
+    fn unbounded_tape_grows_left_without_erroring() {
+        let mut tape = Tape::new(TapeConfig::default());
+        assert!(tape.move_by(0, -1, 0).is_ok());
+        assert_eq!(tape.position(), 0);
+    }
+
+    #[test]
+    fn eof_minus_one_saturates_to_the_cell_width_max_not_zero() {
+        let mut tape = Tape::new(TapeConfig {
+            eof: EofBehavior::MinusOne,
+            overflow: Overflow::Saturate,
+            ..TapeConfig::default()
+        });
+        tape.apply_input(Err(io::Error::from(io::ErrorKind::UnexpectedEof))).unwrap();
+        assert_eq!(tape.get(), CellWidth::U8.max_value());
+    }
+
+    #[test]
+    fn eof_minus_one_wraps_to_the_cell_width_max() {
+        let mut tape = Tape::new(TapeConfig {
+            eof: EofBehavior::MinusOne,
+            overflow: Overflow::Wrap,
+            ..TapeConfig::default()
+        });
+        tape.apply_input(Err(io::Error::from(io::ErrorKind::UnexpectedEof))).unwrap();
+        assert_eq!(tape.get(), CellWidth::U8.max_value());
+    }
+}